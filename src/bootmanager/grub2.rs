@@ -0,0 +1,320 @@
+use super::BootManager;
+use anyhow::{bail, Context, Result};
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::process::Command;
+use std::str;
+
+/// The GRUB environment block is a fixed-size region starting with this
+/// header, followed by `key=value\n` entries and padded with `#` to
+/// exactly [`GRUBENV_SIZE`] bytes.
+const GRUBENV_HEADER: &[u8] = b"# GRUB Environment Block\n";
+const GRUBENV_SIZE: usize = 1024;
+
+/// GRUB2 backend: tracks boot state via grubenv variables.
+///
+/// By default the grubenv file is read and rewritten in place; building
+/// with the `grub2-editenv-fallback` feature instead shells out to
+/// `grub2-editenv` for every operation, matching greenboot's historical
+/// behavior.
+pub struct Grub2BootManager;
+
+impl Grub2BootManager {
+    fn locate_grubenv() -> PathBuf {
+        for candidate in ["/boot/grub2/grubenv", "/boot/efi/EFI/redhat/grubenv"] {
+            if Path::new(candidate).is_file() {
+                return PathBuf::from(candidate);
+            }
+        }
+        PathBuf::from("/boot/grub2/grubenv")
+    }
+
+    fn load_grubenv() -> Result<GrubEnv> {
+        let path = Self::locate_grubenv();
+        let bytes = fs::read(&path).with_context(|| format!("reading {}", path.display()))?;
+        GrubEnv::parse(path, &bytes)
+    }
+
+    fn get_boot_counter_direct(&self) -> Option<i32> {
+        let env = Self::load_grubenv().ok()?;
+        let boot_counter = env.get("boot_counter")?;
+        match boot_counter.parse::<i32>() {
+            Ok(count) => Some(count),
+            Err(_) => {
+                log::error!("boot_counter not a valid integer");
+                None
+            }
+        }
+    }
+
+    fn set_boot_counter_direct(&self, reboot_count: i32) -> Result<()> {
+        match self.get_boot_counter_direct() {
+            Some(counter) => {
+                log::info!("boot_counter={counter}");
+                Ok(())
+            }
+            None => {
+                let mut env = Self::load_grubenv()?;
+                env.set("boot_counter", reboot_count.to_string());
+                env.save()?;
+                log::info!("boot_counter={reboot_count}");
+                Ok(())
+            }
+        }
+    }
+
+    fn unset_boot_counter_direct(&self) -> Result<()> {
+        let mut env = Self::load_grubenv()?;
+        env.unset("boot_counter");
+        env.save()
+    }
+
+    fn set_boot_success_direct(&self, success: bool) -> Result<()> {
+        let mut env = Self::load_grubenv()?;
+        if success {
+            env.set("boot_success", "1");
+            env.unset("boot_counter");
+        } else {
+            env.set("boot_success", "0");
+        }
+        env.save()
+    }
+
+    fn get_boot_counter_editenv(&self) -> Option<i32> {
+        let grub_vars = Command::new("grub2-editenv").arg("-").arg("list").output();
+        if grub_vars.is_err() {
+            return None;
+        }
+        let grub_vars = grub_vars.unwrap();
+        let grub_vars = match str::from_utf8(&grub_vars.stdout[..]) {
+            Ok(vars) => vars.split('\n'),
+            Err(_) => {
+                log::error!("Unable to fetch grub variables");
+                return None;
+            }
+        };
+
+        for var in grub_vars {
+            if var.contains("boot_counter") {
+                let boot_counter = var.split('=').next_back();
+
+                match boot_counter.unwrap().parse::<i32>() {
+                    Ok(count) => return Some(count),
+                    Err(_) => {
+                        log::error!("boot_counter not a valid integer");
+                        return None;
+                    }
+                }
+            }
+        }
+        None
+    }
+
+    fn set_boot_counter_editenv(&self, reboot_count: i32) -> Result<()> {
+        match self.get_boot_counter_editenv() {
+            Some(counter) => {
+                log::info!("boot_counter={counter}");
+                Ok(())
+            }
+            None => {
+                Command::new("grub2-editenv")
+                    .arg("-")
+                    .arg("set")
+                    .arg(format!("boot_counter={reboot_count}"))
+                    .status()?;
+                log::info!("boot_counter={reboot_count}");
+                Ok(())
+            }
+        }
+    }
+
+    fn unset_boot_counter_editenv(&self) -> Result<()> {
+        Command::new("grub2-editenv")
+            .arg("-")
+            .arg("unset")
+            .arg("boot_counter")
+            .status()?;
+        Ok(())
+    }
+
+    fn set_boot_success_editenv(&self, success: bool) -> Result<()> {
+        if success {
+            Command::new("grub2-editenv")
+                .arg("-")
+                .arg("set")
+                .arg("boot_success=1")
+                .status()?;
+            Command::new("grub2-editenv")
+                .arg("-")
+                .arg("unset")
+                .arg("boot_counter")
+                .status()?;
+        } else {
+            Command::new("grub2-editenv")
+                .arg("-")
+                .arg("set")
+                .arg("boot_success=0")
+                .status()?;
+        }
+        Ok(())
+    }
+}
+
+impl BootManager for Grub2BootManager {
+    fn get_boot_counter(&self) -> Option<i32> {
+        if cfg!(feature = "grub2-editenv-fallback") {
+            self.get_boot_counter_editenv()
+        } else {
+            self.get_boot_counter_direct()
+        }
+    }
+
+    fn set_boot_counter(&self, reboot_count: i32) -> Result<()> {
+        if cfg!(feature = "grub2-editenv-fallback") {
+            self.set_boot_counter_editenv(reboot_count)
+        } else {
+            self.set_boot_counter_direct(reboot_count)
+        }
+    }
+
+    fn unset_boot_counter(&self) -> Result<()> {
+        if cfg!(feature = "grub2-editenv-fallback") {
+            self.unset_boot_counter_editenv()
+        } else {
+            self.unset_boot_counter_direct()
+        }
+    }
+
+    fn set_boot_success(&self, success: bool) -> Result<()> {
+        if cfg!(feature = "grub2-editenv-fallback") {
+            self.set_boot_success_editenv(success)
+        } else {
+            self.set_boot_success_direct(success)
+        }
+    }
+
+    fn reboot(&self) -> Result<()> {
+        let status = Command::new("systemctl").arg("reboot").status()?;
+        if !status.success() {
+            bail!("systemctl reboot exited with {status}");
+        }
+        Ok(())
+    }
+}
+
+/// In-memory view of a grubenv file: the ordered `key=value` entries
+/// between the header and the trailing `#` padding.
+struct GrubEnv {
+    path: PathBuf,
+    entries: Vec<(String, String)>,
+}
+
+impl GrubEnv {
+    fn parse(path: PathBuf, bytes: &[u8]) -> Result<Self> {
+        if bytes.len() < GRUBENV_HEADER.len() || &bytes[..GRUBENV_HEADER.len()] != GRUBENV_HEADER {
+            bail!(
+                "{} is missing the GRUB environment block header",
+                path.display()
+            );
+        }
+        let body =
+            str::from_utf8(&bytes[GRUBENV_HEADER.len()..]).context("grubenv is not valid utf-8")?;
+        let entries = body
+            .split('\n')
+            .take_while(|line| !line.is_empty() && !line.starts_with('#'))
+            .filter_map(|line| line.split_once('='))
+            .map(|(k, v)| (k.to_string(), v.to_string()))
+            .collect();
+        Ok(Self { path, entries })
+    }
+
+    fn get(&self, key: &str) -> Option<&str> {
+        self.entries
+            .iter()
+            .find(|(k, _)| k == key)
+            .map(|(_, v)| v.as_str())
+    }
+
+    fn set(&mut self, key: &str, value: impl Into<String>) {
+        let value = value.into();
+        match self.entries.iter_mut().find(|(k, _)| k == key) {
+            Some((_, v)) => *v = value,
+            None => self.entries.push((key.to_string(), value)),
+        }
+    }
+
+    fn unset(&mut self, key: &str) {
+        self.entries.retain(|(k, _)| k != key);
+    }
+
+    /// Re-serializes the header and entries, pads back out to
+    /// [`GRUBENV_SIZE`] bytes with `#`, and atomically rewrites the file.
+    fn save(&self) -> Result<()> {
+        let mut buf = Vec::with_capacity(GRUBENV_SIZE);
+        buf.extend_from_slice(GRUBENV_HEADER);
+        for (k, v) in &self.entries {
+            buf.extend_from_slice(format!("{k}={v}\n").as_bytes());
+        }
+        if buf.len() > GRUBENV_SIZE {
+            bail!("grubenv contents exceed {GRUBENV_SIZE} bytes");
+        }
+        buf.resize(GRUBENV_SIZE, b'#');
+
+        let tmp_path = self.path.with_extension("tmp");
+        fs::write(&tmp_path, &buf)?;
+        fs::rename(&tmp_path, &self.path)?;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn sample_grubenv() -> Vec<u8> {
+        let mut buf = Vec::new();
+        buf.extend_from_slice(GRUBENV_HEADER);
+        buf.extend_from_slice(b"boot_success=1\n");
+        buf.extend_from_slice(b"boot_counter=3\n");
+        buf.resize(GRUBENV_SIZE, b'#');
+        buf
+    }
+
+    #[test]
+    fn round_trips_known_buffer() {
+        let bytes = sample_grubenv();
+        let path = std::env::temp_dir().join("greenboot-grubenv-test-round-trip");
+        let env = GrubEnv::parse(path.clone(), &bytes).unwrap();
+        assert_eq!(env.get("boot_success"), Some("1"));
+        assert_eq!(env.get("boot_counter"), Some("3"));
+
+        env.save().unwrap();
+        let saved = fs::read(&path).unwrap();
+        fs::remove_file(&path).ok();
+        assert_eq!(saved, bytes);
+    }
+
+    #[test]
+    fn set_and_unset_preserve_padding() {
+        let bytes = sample_grubenv();
+        let path = std::env::temp_dir().join("greenboot-grubenv-test-padding");
+        let mut env = GrubEnv::parse(path.clone(), &bytes).unwrap();
+        env.set("boot_counter", "2");
+        env.unset("boot_success");
+
+        env.save().unwrap();
+        let buf = fs::read(&path).unwrap();
+        fs::remove_file(&path).ok();
+        let env = GrubEnv::parse(path, &buf).unwrap();
+
+        assert_eq!(buf.len(), GRUBENV_SIZE);
+        assert_eq!(env.get("boot_counter"), Some("2"));
+        assert_eq!(env.get("boot_success"), None);
+    }
+
+    #[test]
+    fn rejects_missing_header() {
+        let bytes = vec![b'#'; GRUBENV_SIZE];
+        assert!(GrubEnv::parse(PathBuf::from("test-grubenv"), &bytes).is_err());
+    }
+}
@@ -0,0 +1,70 @@
+mod grub2;
+mod systemd_boot;
+
+use anyhow::Result;
+use config::{Config, File, FileFormat};
+use std::path::Path;
+
+use grub2::Grub2BootManager;
+use systemd_boot::SystemdBootManager;
+
+/// Abstraction over the bootloader-specific mechanism greenboot uses to
+/// count boot attempts, mark boot success and trigger a reboot, so the
+/// rest of greenboot doesn't need to know whether it's running on GRUB2
+/// or a systemd-boot/BLS image-based system.
+pub trait BootManager: Send + Sync {
+    fn get_boot_counter(&self) -> Option<i32>;
+    fn set_boot_counter(&self, reboot_count: i32) -> Result<()>;
+    fn unset_boot_counter(&self) -> Result<()>;
+    fn set_boot_success(&self, success: bool) -> Result<()>;
+    fn reboot(&self) -> Result<()>;
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Bootloader {
+    Grub2,
+    SystemdBoot,
+}
+
+impl Bootloader {
+    fn from_config(value: &str) -> Option<Self> {
+        match value.to_lowercase().as_str() {
+            "grub2" => Some(Bootloader::Grub2),
+            "systemd-boot" => Some(Bootloader::SystemdBoot),
+            _ => None,
+        }
+    }
+
+    /// Autodetects the bootloader in use when `GREENBOOT_BOOTLOADER` isn't
+    /// set in greenboot.conf: systemd-boot/BLS systems carry a
+    /// `loader/loader.conf` on the ESP, GRUB2 systems don't.
+    fn detect() -> Self {
+        if Path::new("/boot/efi/loader/loader.conf").is_file()
+            || Path::new("/boot/loader/loader.conf").is_file()
+        {
+            Bootloader::SystemdBoot
+        } else {
+            Bootloader::Grub2
+        }
+    }
+
+    fn configured() -> Self {
+        let parsed = Config::builder()
+            .add_source(File::new(crate::GREENBOOT_CONFIG_FILE, FileFormat::Ini))
+            .build();
+        let configured = parsed
+            .ok()
+            .and_then(|c| c.get_string("GREENBOOT_BOOTLOADER").ok())
+            .and_then(|v| Bootloader::from_config(&v));
+        configured.unwrap_or_else(Bootloader::detect)
+    }
+}
+
+/// Returns the [`BootManager`] for the bootloader configured (or
+/// autodetected) on this system.
+pub fn boot_manager() -> Box<dyn BootManager> {
+    match Bootloader::configured() {
+        Bootloader::Grub2 => Box::new(Grub2BootManager),
+        Bootloader::SystemdBoot => Box::new(SystemdBootManager::detect()),
+    }
+}
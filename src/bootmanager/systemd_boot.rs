@@ -0,0 +1,294 @@
+use super::BootManager;
+use anyhow::{anyhow, bail, Context, Result};
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::process::Command;
+
+/// Locates the well-known systemd-boot/Boot Loader Specification paths on
+/// the EFI System Partition, mirroring lanzaboote's `EspPaths`: a
+/// `loader/loader.conf` and a `loader/entries/` directory holding one
+/// `.conf` file per generation.
+struct EspPaths {
+    entries_dir: PathBuf,
+    loader_conf: PathBuf,
+}
+
+impl EspPaths {
+    fn new(esp: impl AsRef<Path>) -> Self {
+        let esp = esp.as_ref();
+        Self {
+            entries_dir: esp.join("loader/entries"),
+            loader_conf: esp.join("loader/loader.conf"),
+        }
+    }
+
+    fn detect() -> Self {
+        for candidate in ["/boot/efi", "/boot"] {
+            let paths = Self::new(candidate);
+            if paths.entries_dir.is_dir() {
+                return paths;
+            }
+        }
+        Self::new("/boot/efi")
+    }
+
+    /// The glob pattern from loader.conf's `default=<glob>` line, per the
+    /// BLS spec, if one is set.
+    fn default_pattern(&self) -> Option<glob::Pattern> {
+        let contents = fs::read_to_string(&self.loader_conf).ok()?;
+        contents.lines().find_map(|line| {
+            let value = line.trim().strip_prefix("default")?;
+            let value = value.trim_start_matches(['=', ' ', '\t']).trim();
+            if value.is_empty() {
+                return None;
+            }
+            glob::Pattern::new(value).ok()
+        })
+    }
+}
+
+/// systemd-boot/BLS backend: tracks the boot counter via the
+/// `boot-counting` filename suffix on the default loader entry
+/// (`entry+3.conf` -> `entry+2-1.conf` -> ... as defined by the BLS spec),
+/// instead of a grubenv variable.
+pub struct SystemdBootManager {
+    esp: EspPaths,
+}
+
+impl SystemdBootManager {
+    pub fn detect() -> Self {
+        Self {
+            esp: EspPaths::detect(),
+        }
+    }
+
+    /// The entry file greenboot tracks: the one named by loader.conf's
+    /// `default=` glob, per the BLS spec, or -- if that's absent or
+    /// matches nothing -- the newest entry by a version-aware ("natural")
+    /// sort, since a plain lexicographic sort gets multi-digit version
+    /// suffixes backwards (e.g. `"...-5.10.0...."` sorting before
+    /// `"...-5.9.0...."`).
+    fn default_entry(&self) -> Result<PathBuf> {
+        let entries: Vec<PathBuf> = fs::read_dir(&self.esp.entries_dir)
+            .with_context(|| format!("cannot read {}", self.esp.entries_dir.display()))?
+            .flatten()
+            .map(|e| e.path())
+            .filter(|p| p.extension().is_some_and(|ext| ext == "conf"))
+            .collect();
+
+        if let Some(pattern) = self.esp.default_pattern() {
+            // loader.conf's `default=` value is a full filename (extension
+            // included, per the BLS spec), so match it against the full
+            // filename rather than the extension-less stem.
+            let matches: Vec<PathBuf> = entries
+                .iter()
+                .filter(|p| {
+                    p.file_name()
+                        .and_then(|s| s.to_str())
+                        .is_some_and(|name| pattern.matches(name))
+                })
+                .cloned()
+                .collect();
+            if let Some(entry) = Self::newest(&matches) {
+                return Ok(entry);
+            }
+        }
+
+        Self::newest(&entries).ok_or_else(|| {
+            anyhow!(
+                "no loader entries found under {}",
+                self.esp.entries_dir.display()
+            )
+        })
+    }
+
+    /// Picks the entry whose filename stem sorts highest under a
+    /// version-aware comparison.
+    fn newest(entries: &[PathBuf]) -> Option<PathBuf> {
+        entries
+            .iter()
+            .max_by(|a, b| natural_key(&stem(a)).cmp(&natural_key(&stem(b))))
+            .cloned()
+    }
+
+    /// Splits `<stem>+<left>[-<done>].conf` into its counting components.
+    fn parse_counter(entry: &Path) -> Option<(String, i32, i32)> {
+        let stem = entry.file_stem()?.to_str()?;
+        let (stem, counter) = stem.split_once('+')?;
+        let (left, done) = match counter.split_once('-') {
+            Some((left, done)) => (left.parse().ok()?, done.parse().ok()?),
+            None => (counter.parse().ok()?, 0),
+        };
+        Some((stem.to_string(), left, done))
+    }
+
+    fn rename_to(&self, entry: &Path, new_name: &str) -> Result<PathBuf> {
+        let new_path = self.esp.entries_dir.join(format!("{new_name}.conf"));
+        fs::rename(entry, &new_path)?;
+        Ok(new_path)
+    }
+}
+
+fn stem(path: &Path) -> String {
+    path.file_stem()
+        .and_then(|s| s.to_str())
+        .unwrap_or_default()
+        .to_string()
+}
+
+#[derive(Debug, PartialEq, Eq, PartialOrd, Ord)]
+enum NaturalPart {
+    Num(u64),
+    Text(String),
+}
+
+/// Splits a string into alternating runs of digits and non-digits so
+/// runs of digits compare numerically instead of lexicographically
+/// (`"5.10.0"` > `"5.9.0"`).
+fn natural_key(s: &str) -> Vec<NaturalPart> {
+    let mut parts = Vec::new();
+    let mut chars = s.chars().peekable();
+    while let Some(&c) = chars.peek() {
+        let mut run = String::new();
+        let is_digit_run = c.is_ascii_digit();
+        while let Some(&d) = chars.peek() {
+            if d.is_ascii_digit() == is_digit_run {
+                run.push(d);
+                chars.next();
+            } else {
+                break;
+            }
+        }
+        parts.push(if is_digit_run {
+            NaturalPart::Num(run.parse().unwrap_or(0))
+        } else {
+            NaturalPart::Text(run)
+        });
+    }
+    parts
+}
+
+impl BootManager for SystemdBootManager {
+    fn get_boot_counter(&self) -> Option<i32> {
+        let entry = self.default_entry().ok()?;
+        Self::parse_counter(&entry).map(|(_, left, _)| left)
+    }
+
+    fn set_boot_counter(&self, reboot_count: i32) -> Result<()> {
+        let entry = self.default_entry()?;
+        match Self::parse_counter(&entry) {
+            Some((_, left, _)) => {
+                log::info!("boot_counter={left}");
+                Ok(())
+            }
+            None => {
+                let stem = entry
+                    .file_stem()
+                    .and_then(|s| s.to_str())
+                    .ok_or_else(|| anyhow!("invalid entry filename: {}", entry.display()))?;
+                self.rename_to(&entry, &format!("{stem}+{reboot_count}"))?;
+                log::info!("boot_counter={reboot_count}");
+                Ok(())
+            }
+        }
+    }
+
+    fn unset_boot_counter(&self) -> Result<()> {
+        let entry = self.default_entry()?;
+        if let Some((stem, ..)) = Self::parse_counter(&entry) {
+            self.rename_to(&entry, &stem)?;
+        }
+        Ok(())
+    }
+
+    fn set_boot_success(&self, success: bool) -> Result<()> {
+        // BLS has no `boot_success` variable of its own; a successful boot
+        // is recorded the same way a manually-confirmed entry is: by
+        // stripping the counting suffix so systemd-boot stops decrementing
+        // it. A failed boot just leaves the counter as-is for the next
+        // attempt to decrement.
+        if success {
+            self.unset_boot_counter()?;
+        }
+        Ok(())
+    }
+
+    fn reboot(&self) -> Result<()> {
+        let status = Command::new("systemctl").arg("reboot").status()?;
+        if !status.success() {
+            bail!("systemctl reboot exited with {status}");
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn temp_esp(name: &str) -> PathBuf {
+        let dir = std::env::temp_dir().join(format!("greenboot-systemd-boot-test-{name}"));
+        fs::remove_dir_all(&dir).ok();
+        fs::create_dir_all(dir.join("loader/entries")).unwrap();
+        dir
+    }
+
+    #[test]
+    fn parse_counter_splits_left_and_done() {
+        assert_eq!(
+            SystemdBootManager::parse_counter(Path::new("fedora-5.10.0+3.conf")),
+            Some(("fedora-5.10.0".to_string(), 3, 0))
+        );
+        assert_eq!(
+            SystemdBootManager::parse_counter(Path::new("fedora-5.10.0+2-1.conf")),
+            Some(("fedora-5.10.0".to_string(), 2, 1))
+        );
+        assert_eq!(
+            SystemdBootManager::parse_counter(Path::new("fedora-5.10.0.conf")),
+            None
+        );
+    }
+
+    #[test]
+    fn natural_key_orders_multi_digit_versions_correctly() {
+        assert!(natural_key("fedora-5.10.0") > natural_key("fedora-5.9.0"));
+        assert!(natural_key("fedora-5.9.0") < natural_key("fedora-5.10.0"));
+    }
+
+    #[test]
+    fn default_entry_falls_back_to_natural_sort() {
+        let esp_root = temp_esp("natural-sort");
+        let entries_dir = esp_root.join("loader/entries");
+        fs::write(entries_dir.join("fedora-5.9.0.conf"), "").unwrap();
+        fs::write(entries_dir.join("fedora-5.10.0.conf"), "").unwrap();
+
+        let manager = SystemdBootManager {
+            esp: EspPaths::new(&esp_root),
+        };
+        let entry = manager.default_entry().unwrap();
+        assert_eq!(entry.file_name().unwrap(), "fedora-5.10.0.conf");
+
+        fs::remove_dir_all(&esp_root).ok();
+    }
+
+    #[test]
+    fn default_entry_prefers_loader_conf_default_over_sort_order() {
+        let esp_root = temp_esp("loader-conf-default");
+        let entries_dir = esp_root.join("loader/entries");
+        fs::write(entries_dir.join("fedora-5.9.0.conf"), "").unwrap();
+        fs::write(entries_dir.join("fedora-5.10.0.conf"), "").unwrap();
+        fs::write(
+            esp_root.join("loader/loader.conf"),
+            "default fedora-5.9.0.conf\n",
+        )
+        .unwrap();
+
+        let manager = SystemdBootManager {
+            esp: EspPaths::new(&esp_root),
+        };
+        let entry = manager.default_entry().unwrap();
+        assert_eq!(entry.file_name().unwrap(), "fedora-5.9.0.conf");
+
+        fs::remove_dir_all(&esp_root).ok();
+    }
+}
@@ -1,8 +1,58 @@
+use crate::bootmanager::{self, BootManager};
+use crate::retry;
 use anyhow::{bail, Error, Result};
+use config::{Config, File, FileFormat};
 use std::fs::OpenOptions;
 use std::io::Write;
 use std::process::Command;
-use std::str;
+use std::sync::OnceLock;
+use std::time::Duration;
+
+static BOOT_MANAGER: OnceLock<Box<dyn BootManager>> = OnceLock::new();
+
+fn manager() -> &'static dyn BootManager {
+    BOOT_MANAGER.get_or_init(bootmanager::boot_manager).as_ref()
+}
+
+/// Retry attempt counts for the reboot/rollback commands, from
+/// `GREENBOOT_REBOOT_RETRIES` / `GREENBOOT_ROLLBACK_RETRIES`, and an
+/// optional backoff ceiling from `GREENBOOT_RETRY_BACKOFF_CEILING_MS`.
+struct RetryConfig {
+    reboot_attempts: u32,
+    rollback_attempts: u32,
+    backoff_ceiling: Option<Duration>,
+}
+
+impl RetryConfig {
+    fn load() -> Self {
+        let mut config = Self {
+            reboot_attempts: 3,
+            rollback_attempts: 3,
+            backoff_ceiling: None,
+        };
+        let parsed = Config::builder()
+            .add_source(File::new(crate::GREENBOOT_CONFIG_FILE, FileFormat::Ini))
+            .build();
+        if let Ok(c) = parsed {
+            if let Ok(v) = c.get_int("GREENBOOT_REBOOT_RETRIES") {
+                config.reboot_attempts = v.max(1) as u32;
+            }
+            if let Ok(v) = c.get_int("GREENBOOT_ROLLBACK_RETRIES") {
+                config.rollback_attempts = v.max(1) as u32;
+            }
+            if let Ok(v) = c.get_int("GREENBOOT_RETRY_BACKOFF_CEILING_MS") {
+                config.backoff_ceiling = Some(Duration::from_millis(v.max(0) as u64));
+            }
+        }
+        config
+    }
+}
+
+static RETRY_CONFIG: OnceLock<RetryConfig> = OnceLock::new();
+
+fn retry_config() -> &'static RetryConfig {
+    RETRY_CONFIG.get_or_init(RetryConfig::load)
+}
 
 pub fn handle_reboot(force: bool) -> Result<(), Error> {
     if !force {
@@ -13,71 +63,83 @@ pub fn handle_reboot(force: bool) -> Result<(), Error> {
         }
     }
     log::info!("restarting system");
-    Command::new("systemctl").arg("reboot").status()?;
-    Ok(())
+    let config = retry_config();
+    retry::retry(config.reboot_attempts, config.backoff_ceiling, || {
+        manager().reboot()
+    })
 }
 
-pub fn handle_rollback() -> Result<(), Error> {
+/// Outcome of [`handle_rollback`]: a rollback was actually performed, or
+/// there was no previous deployment to roll back to (a non-fatal state,
+/// e.g. on a freshly installed system).
+#[derive(Debug, PartialEq, Eq)]
+pub enum RollbackOutcome {
+    RolledBack,
+    NoRollbackTarget,
+}
+
+pub fn handle_rollback() -> Result<RollbackOutcome, Error> {
     match get_boot_counter() {
         Some(t) if t <= 0 => {
-            log::info!("Greenboot will now attempt rollback");
-            let status = Command::new("rpm-ostree").arg("rollback").status()?;
-            if status.success() {
-                return Ok(());
+            if crate::ostree::deployments()?.len() < 2 {
+                log::warn!("only one deployment present, no rollback target available");
+                return Ok(RollbackOutcome::NoRollbackTarget);
             }
-            bail!(status.to_string());
+            log::info!("Greenboot will now attempt rollback");
+            let config = retry_config();
+            retry::retry(config.rollback_attempts, config.backoff_ceiling, || {
+                let status = Command::new("rpm-ostree").arg("rollback").status()?;
+                if status.success() {
+                    Ok(())
+                } else {
+                    bail!(status.to_string())
+                }
+            })?;
+            Ok(RollbackOutcome::RolledBack)
         }
         _ => bail!("boot_counter is either unset or not equal to 0"),
     }
 }
 
-pub fn set_boot_counter(reboot_count: i32) -> Result<()> {
+/// Like [`handle_rollback`], but targets a specific deployment checksum
+/// (via `rpm-ostree deploy`) instead of rolling back to whatever
+/// deployment happens to be inactive.
+pub fn handle_rollback_to(checksum: &str) -> Result<RollbackOutcome, Error> {
     match get_boot_counter() {
-        Some(counter) => {
-            log::info!("boot_counter={counter}");
-            Ok(())
-        }
-        None => {
-            Command::new("grub2-editenv")
-                .arg("-")
-                .arg("set")
-                .arg(format!("boot_counter={reboot_count}"))
-                .status()?;
-            log::info!("boot_counter={reboot_count}");
-            Ok(())
+        Some(t) if t <= 0 => {
+            if crate::ostree::deployments()?.len() < 2 {
+                log::warn!("only one deployment present, no rollback target available");
+                return Ok(RollbackOutcome::NoRollbackTarget);
+            }
+            log::info!("Greenboot will now attempt rollback to known-good deployment {checksum}");
+            let config = retry_config();
+            retry::retry(config.rollback_attempts, config.backoff_ceiling, || {
+                let status = Command::new("rpm-ostree")
+                    .arg("deploy")
+                    .arg(checksum)
+                    .status()?;
+                if status.success() {
+                    Ok(())
+                } else {
+                    bail!(status.to_string())
+                }
+            })?;
+            Ok(RollbackOutcome::RolledBack)
         }
+        _ => bail!("boot_counter is either unset or not equal to 0"),
     }
 }
 
+pub fn set_boot_counter(reboot_count: i32) -> Result<()> {
+    manager().set_boot_counter(reboot_count)
+}
+
 pub fn unset_boot_counter() -> Result<()> {
-    Command::new("grub2-editenv")
-        .arg("-")
-        .arg("unset")
-        .arg("boot_counter")
-        .status()?;
-    Ok(())
+    manager().unset_boot_counter()
 }
 
 pub fn handle_boot_success(success: bool) -> Result<()> {
-    if success {
-        Command::new("grub2-editenv")
-            .arg("-")
-            .arg("set")
-            .arg("boot_success=1")
-            .status()?;
-        Command::new("grub2-editenv")
-            .arg("-")
-            .arg("unset")
-            .arg("boot_counter")
-            .status()?;
-    } else {
-        Command::new("grub2-editenv")
-            .arg("-")
-            .arg("set")
-            .arg("boot_success=0")
-            .status()?;
-    }
-    Ok(())
+    manager().set_boot_success(success)
 }
 
 pub fn handle_motd(state: &str) -> Result<(), Error> {
@@ -86,37 +148,12 @@ pub fn handle_motd(state: &str) -> Result<(), Error> {
     let mut motd_file = OpenOptions::new()
         .create(true)
         .write(true)
+        .truncate(true)
         .open("/etc/motd.d/boot-status")?;
     motd_file.write_all(motd.as_bytes())?;
     Ok(())
 }
 
 pub fn get_boot_counter() -> Option<i32> {
-    let grub_vars = Command::new("grub2-editenv").arg("-").arg("list").output();
-    if grub_vars.is_err() {
-        return None;
-    }
-    let grub_vars = grub_vars.unwrap();
-    let grub_vars = match str::from_utf8(&grub_vars.stdout[..]) {
-        Ok(vars) => vars.split('\n'),
-        Err(_) => {
-            log::error!("Unable to fetch grub variables");
-            return None;
-        }
-    };
-
-    for var in grub_vars {
-        if var.contains("boot_counter") {
-            let boot_counter = var.split('=').last();
-
-            match boot_counter.unwrap().parse::<i32>() {
-                Ok(count) => return Some(count),
-                Err(_) => {
-                    log::error!("boot_counter not a valid integer");
-                    return None;
-                }
-            }
-        }
-    }
-    None
+    manager().get_boot_counter()
 }
@@ -0,0 +1,115 @@
+use crate::ostree::Deployment;
+use anyhow::Result;
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::path::Path;
+
+const STATE_FILE: &str = "/etc/greenboot/known_good.json";
+
+/// A deployment that passed a GREEN health-check, recorded so a later
+/// rollback can target a deployment that is actually known to work
+/// rather than just "the previous one".
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+pub struct KnownGoodDeployment {
+    pub checksum: String,
+    pub timestamp: u64,
+}
+
+/// Newest-first list of the last `N` deployments that passed a
+/// health-check.
+#[derive(Debug, Default, Serialize, Deserialize)]
+struct KnownGoodState {
+    deployments: Vec<KnownGoodDeployment>,
+}
+
+fn load(path: &Path) -> KnownGoodState {
+    fs::read_to_string(path)
+        .ok()
+        .and_then(|s| serde_json::from_str(&s).ok())
+        .unwrap_or_default()
+}
+
+fn save(path: &Path, state: &KnownGoodState) -> Result<()> {
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent)?;
+    }
+    fs::write(path, serde_json::to_string_pretty(state)?)?;
+    Ok(())
+}
+
+/// Records `deployment` as known-good, keeping only the newest `limit`
+/// entries (at least one, even when `limit` is 0).
+pub fn record(deployment: &Deployment, limit: usize) -> Result<()> {
+    record_at(Path::new(STATE_FILE), deployment, limit)
+}
+
+fn record_at(path: &Path, deployment: &Deployment, limit: usize) -> Result<()> {
+    let mut state = load(path);
+    state
+        .deployments
+        .retain(|d| d.checksum != deployment.checksum);
+    state.deployments.insert(
+        0,
+        KnownGoodDeployment {
+            checksum: deployment.checksum.clone(),
+            timestamp: deployment.timestamp,
+        },
+    );
+    state.deployments.truncate(limit.max(1));
+    save(path, &state)
+}
+
+/// Returns the newest recorded known-good deployment, if any.
+pub fn last() -> Option<KnownGoodDeployment> {
+    last_at(Path::new(STATE_FILE))
+}
+
+fn last_at(path: &Path) -> Option<KnownGoodDeployment> {
+    load(path).deployments.into_iter().next()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::path::PathBuf;
+
+    fn temp_path(name: &str) -> PathBuf {
+        std::env::temp_dir().join(format!("greenboot-known-good-test-{name}.json"))
+    }
+
+    fn deployment(checksum: &str, timestamp: u64) -> Deployment {
+        Deployment {
+            checksum: checksum.to_string(),
+            timestamp,
+        }
+    }
+
+    #[test]
+    fn records_and_retains_limit() {
+        let path = temp_path("limit");
+        fs::remove_file(&path).ok();
+
+        for i in 0..5 {
+            record_at(&path, &deployment(&format!("deploy-{i}"), i as u64), 3).unwrap();
+        }
+
+        let state = load(&path);
+        assert_eq!(state.deployments.len(), 3);
+        assert_eq!(state.deployments[0].checksum, "deploy-4");
+        assert_eq!(state.deployments[2].checksum, "deploy-2");
+
+        fs::remove_file(&path).ok();
+    }
+
+    #[test]
+    fn last_returns_newest() {
+        let path = temp_path("last");
+        fs::remove_file(&path).ok();
+        assert!(last_at(&path).is_none());
+
+        record_at(&path, &deployment("abc", 1), 3).unwrap();
+
+        assert_eq!(last_at(&path).unwrap().checksum, "abc");
+        fs::remove_file(&path).ok();
+    }
+}
@@ -1,19 +1,22 @@
+mod bootmanager;
 mod handler;
+mod known_good;
+mod ostree;
+mod retry;
+mod service_monitor;
 use anyhow::{bail, Error, Result};
 use clap::{Parser, Subcommand, ValueEnum};
 use config::{Config, File, FileFormat};
 use glob::glob;
 use handler::*;
-use nix::sys::socket::SockaddrLike;
 use serde::Deserialize;
+use service_monitor::ServiceMonitorResult;
 use std::path::Path;
 use std::process::Command;
-use std::str;
 use std::time::{Duration, SystemTime};
-use systemctl;
 
 static GREENBOOT_INSTALL_PATHS: [&str; 2] = ["/usr/lib/greenboot", "/etc/greenboot"];
-static GREENBOOT_CONFIG_FILE: &str = "/etc/greenboot/greenboot.conf";
+pub(crate) static GREENBOOT_CONFIG_FILE: &str = "/etc/greenboot/greenboot.conf";
 
 #[derive(Parser)]
 #[clap(author, version, about, long_about = None)]
@@ -28,11 +31,19 @@ struct Cli {
 struct GreenbootConfig {
     //max reboot attempts if diagnostics fails
     max_reboot: i32,
+    //units watched by the service monitor, from GREENBOOT_MONITOR_SERVICES
+    monitor_services: Vec<String>,
+    //how many known-good deployments to retain, from GREENBOOT_KNOWN_GOOD_LIMIT
+    known_good_limit: usize,
 }
 
 impl GreenbootConfig {
     fn set_default() -> Self {
-        Self { max_reboot: 3 }
+        Self {
+            max_reboot: 3,
+            monitor_services: Vec::new(),
+            known_good_limit: 3,
+        }
     }
 
     fn get_config() -> Self {
@@ -51,7 +62,29 @@ impl GreenbootConfig {
                         log::warn!("{e}, using default value");
                         config.max_reboot
                     }
-                }
+                };
+                config.monitor_services = match c.get_string("GREENBOOT_MONITOR_SERVICES") {
+                    Ok(v) => v
+                        .split(',')
+                        .map(str::trim)
+                        .filter(|s| !s.is_empty())
+                        .map(String::from)
+                        .collect(),
+                    Err(e) => {
+                        log::warn!("{e}, no services monitored");
+                        config.monitor_services
+                    }
+                };
+                config.known_good_limit = match c.get_int("GREENBOOT_KNOWN_GOOD_LIMIT") {
+                    Ok(c) => c.try_into().unwrap_or_else(|e| {
+                        log::warn!("{e}, using default value");
+                        config.known_good_limit
+                    }),
+                    Err(e) => {
+                        log::warn!("{e}, using default value");
+                        config.known_good_limit
+                    }
+                };
             }
             Err(e) => log::warn!("{e}, using default value"),
         }
@@ -87,7 +120,8 @@ enum Commands {
     HealthCheck,
     Rollback,
     PocRollback,
-    PocServiceMinitor,
+    ServiceMonitor,
+    LastKnownGood,
 }
 
 fn run_diagnostics() -> Result<(), Error> {
@@ -170,62 +204,116 @@ fn health_check() -> Result<()> {
     let config = GreenbootConfig::get_config();
     log::info!("{config:?}");
     handle_motd("healthcheck is in progress").ok();
+
+    let service_state = service_monitor::check(&config.monitor_services);
+    if let ServiceMonitorResult::CriticalFailure(units) = &service_state {
+        log::error!("critical service failure, needs manual intervention: {units:?}");
+        handle_motd("healthcheck failed - status is RED")
+            .unwrap_or_else(|e| log::error!("cannot set motd due to : {e}"));
+        run_red()
+            .unwrap_or_else(|e| log::error!("cannot run red script due to: {e}"));
+        handle_boot_success(false)?;
+        set_boot_counter(config.max_reboot)
+            .unwrap_or_else(|e| log::error!("cannot set boot_counter as: {e}"));
+        handle_reboot(false)
+            .unwrap_or_else(|e| log::error!("cannot reboot as: {e}"));
+        bail!("critical service failure: {units:?}");
+    }
+
     let run_status = run_diagnostics();
+    let run_status = match (run_status, service_state) {
+        (Ok(()), ServiceMonitorResult::RecoverableFailure(units)) => {
+            log::warn!("recoverable service failure: {units:?}");
+            Err(Error::msg(format!("service health check failed: {units:?}")))
+        }
+        (status, _) => status,
+    };
     match run_status {
         Ok(()) => {
             log::info!("greenboot health-check passed.");
             run_green().unwrap_or_else(|e| {
-                log::error!("cannot run green script due to: {}", e.to_string())
+                log::error!("cannot run green script due to: {e}")
             });
             handle_motd("healthcheck passed - status is GREEN")
-                .unwrap_or_else(|e| log::error!("cannot set motd due to : {}", e.to_string()));
+                .unwrap_or_else(|e| log::error!("cannot set motd due to : {e}"));
             handle_boot_success(true)?;
+            match ostree::deployments() {
+                Ok(deployments) => {
+                    if let Some(current) = deployments.first() {
+                        known_good::record(current, config.known_good_limit).unwrap_or_else(|e| {
+                            log::warn!("cannot record known-good deployment: {e}")
+                        });
+                    }
+                }
+                Err(e) => log::warn!("cannot determine current deployment: {e}"),
+            }
             Ok(())
         }
         Err(e) => {
             log::error!("Greenboot health-check failed!");
             handle_motd("healthcheck failed - status is RED")
-                .unwrap_or_else(|e| log::error!("cannot set motd due to : {}", e.to_string()));
+                .unwrap_or_else(|e| log::error!("cannot set motd due to : {e}"));
             run_red()
-                .unwrap_or_else(|e| log::error!("cannot run red script due to: {}", e.to_string()));
+                .unwrap_or_else(|e| log::error!("cannot run red script due to: {e}"));
             handle_boot_success(false)?;
             set_boot_counter(config.max_reboot)
-                .unwrap_or_else(|e| log::error!("cannot set boot_counter as: {}", e.to_string()));
+                .unwrap_or_else(|e| log::error!("cannot set boot_counter as: {e}"));
             handle_reboot(false)
-                .unwrap_or_else(|e| log::error!("cannot reboot as: {}", e.to_string()));
+                .unwrap_or_else(|e| log::error!("cannot reboot as: {e}"));
             bail!(e);
         }
     }
 }
 
 fn trigger_rollback() -> Result<()> {
-    match handle_rollback() {
-        Ok(()) => {
+    // A known-good record can point at the deployment we're currently
+    // booted on (e.g. it passed a health-check on a boot that later
+    // degraded); rolling back to it would be a no-op, so fall back to a
+    // plain rollback in that case instead.
+    let current_checksum = ostree::deployments()
+        .ok()
+        .and_then(|deployments| deployments.into_iter().next())
+        .map(|d| d.checksum);
+    let target = known_good::last().filter(|good| Some(&good.checksum) != current_checksum.as_ref());
+
+    let outcome = match target {
+        Some(good) => handle_rollback_to(&good.checksum),
+        None => handle_rollback(),
+    };
+
+    match outcome {
+        Ok(RollbackOutcome::RolledBack) => {
             log::info!("Rollback successful");
             unset_boot_counter()?;
             handle_reboot(true)?;
             Ok(())
         }
+        Ok(RollbackOutcome::NoRollbackTarget) => {
+            log::warn!("no previous deployment to roll back to, nothing to do");
+            Ok(())
+        }
         Err(e) => {
             bail!("Rollback not initiated as {}", e);
         }
     }
 }
 
-fn poc_rollback_policy(duration: u32) -> Result<()> {
-    let s = Command::new("rpm-ostree")
-        .arg("status")
-        .arg("--json")
-        .output()
-        .unwrap();
-    let j: serde_json::Value = match str::from_utf8(&s.stdout[..]) {
-        Ok(v) => serde_json::from_str(v).unwrap(),
-        Err(_) => bail!("cannot_convert to json"),
+#[derive(Debug, PartialEq, Eq)]
+enum RollbackPolicy {
+    /// a previous deployment exists and the grace period hasn't elapsed
+    Proceed,
+    /// only one deployment is present; there is nothing to roll back to
+    NoRollbackTarget,
+}
+
+fn poc_rollback_policy(duration: u32) -> Result<RollbackPolicy> {
+    let deployments = ostree::deployments()?;
+    let Some(previous) = deployments.get(1) else {
+        log::info!("only one deployment present, no rollback target available");
+        return Ok(RollbackPolicy::NoRollbackTarget);
     };
-    let t_current = &j["deployments"][0]["timestamp"];
-    let t_current_millis = Duration::from_secs(t_current.as_u64().unwrap());
-    let t_previous = &j["deployments"][1]["timestamp"];
-    let t_previous_millis = Duration::from_secs(t_previous.as_u64().unwrap());
+    let t_current_millis = Duration::from_secs(deployments[0].timestamp);
+    let t_previous_millis = Duration::from_secs(previous.timestamp);
     if t_current_millis < t_previous_millis {
         bail!("already in the previous deployment");
     }
@@ -236,89 +324,44 @@ fn poc_rollback_policy(duration: u32) -> Result<()> {
         bail!("grace prediod has already passed to trigger rollback");
     }
     log::info!("within grace period");
-    Ok(())
+    Ok(RollbackPolicy::Proceed)
 }
 
-fn poc_service_monitor(mut services: Vec<&str>) -> Result<()> {
-    
-    //1. check if service exits
-    //2. check if services are enabled
-    //3. check running
-    //4. Reporting
-
-    //this will prioritize after health check and retrun two type of failure 
-    //if step 1 or 2 fail then result of 3 is ignored - Critical error, need manual intervention
-    //if step 1 and 2 passes but 3 fails - Auto revover error, and regular restart procedure is followed
-
-    //Assumptions
-    // let mut services = vec!["sshd","podman"];
-
-    let mut service_not_ok:Vec<&str> = Vec::with_capacity(services.len());
-    let mut unforced_error :bool = false; 
-
-    for service in &services {
-        match systemctl::exists(service) {
-            Ok(service_exists) => {
-                if !service_exists {
-                    log::warn!("service: {service} does not exits");
-                    service_not_ok.push(service);
-                } 
-            },
-            Err(err) => log::error!("Error fetching {service} details: {err}"),
-        }
-    }
-    services.retain(|&v| !service_not_ok.contains(&v));
-
-    for service in &services {
-        match systemctl::Unit::from_systemctl(service) {
-            Ok(service_details) => {
-                match service_details.status(){
-                    Ok(state)  => {
-                            match state.as_str() {
-                                "Enabled" => {
-                                    //check for running status...
-                                    //will modify unforced_error here
-                                    unforced_error=true;
-                                },
-                                _ => {
-                                    service_not_ok.push(service);
-                                    log::warn!("service: {} is not enabled", service_details.name);
-                                },
-                            }; 
-                        }
-                    Err(err) => log::error!("Error fetching {service} status: {err}"),
-                }
-            },
-            Err(err) => log::error!("Error fetching {service} status: {err}"),
-        }
-    }
-
-    if !service_not_ok.is_empty() {
-        bail!("{}",{
-            service_not_ok.dedup();
-            ().len()
-        });
-    }
-
-    if unforced_error {
-        bail!("{}", -1);
-    }
-    Ok(())
-}
 fn main() -> Result<()> {
     let cli = Cli::parse();
     pretty_env_logger::formatted_builder()
         .filter_level(cli.log_level.to_log())
         .init();
 
-    //get_config should be here.....
-    let services = vec!["sshd","podman"];
-    
     match cli.command {
         Commands::HealthCheck => health_check(),
         Commands::Rollback => trigger_rollback(),
-        Commands::PocRollback => poc_rollback_policy(1),
-        Commands::PocServiceMinitor => poc_service_monitor(services),
+        Commands::PocRollback => poc_rollback_policy(1).map(|_| ()),
+        Commands::ServiceMonitor => {
+            let config = GreenbootConfig::get_config();
+            match service_monitor::check(&config.monitor_services) {
+                ServiceMonitorResult::Ok => {
+                    log::info!("all monitored services healthy");
+                    Ok(())
+                }
+                ServiceMonitorResult::CriticalFailure(units) => {
+                    bail!("critical service failure: {units:?}")
+                }
+                ServiceMonitorResult::RecoverableFailure(units) => {
+                    bail!("recoverable service failure: {units:?}")
+                }
+            }
+        }
+        Commands::LastKnownGood => match known_good::last() {
+            Some(good) => {
+                println!("{} ({})", good.checksum, good.timestamp);
+                Ok(())
+            }
+            None => {
+                log::info!("no known-good deployment recorded yet");
+                Ok(())
+            }
+        },
     }
 }
 
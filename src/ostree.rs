@@ -0,0 +1,58 @@
+use anyhow::Result;
+use serde::Deserialize;
+use std::process::Command;
+use std::str;
+
+/// A single rpm-ostree deployment as reported by `rpm-ostree status
+/// --json`. `deployments[0]` is the booted deployment; further entries
+/// are older, inactive ones -- there may be none at all on a freshly
+/// installed system.
+#[derive(Debug, Deserialize, Clone)]
+pub struct Deployment {
+    pub checksum: String,
+    pub timestamp: u64,
+}
+
+#[derive(Debug, Deserialize)]
+struct Status {
+    deployments: Vec<Deployment>,
+}
+
+/// Returns the deployments rpm-ostree currently knows about, booted
+/// deployment first.
+pub fn deployments() -> Result<Vec<Deployment>> {
+    let output = Command::new("rpm-ostree")
+        .arg("status")
+        .arg("--json")
+        .output()?;
+    parse_deployments(str::from_utf8(&output.stdout)?)
+}
+
+fn parse_deployments(json: &str) -> Result<Vec<Deployment>> {
+    let status: Status = serde_json::from_str(json)?;
+    Ok(status.deployments)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn single_deployment_has_no_previous() {
+        let deployments =
+            parse_deployments(r#"{"deployments":[{"checksum":"abc","timestamp":100}]}"#).unwrap();
+        assert_eq!(deployments.len(), 1);
+        assert!(deployments.get(1).is_none());
+    }
+
+    #[test]
+    fn two_deployments_exposes_previous() {
+        let deployments = parse_deployments(
+            r#"{"deployments":[{"checksum":"abc","timestamp":200},{"checksum":"def","timestamp":100}]}"#,
+        )
+        .unwrap();
+        assert_eq!(deployments.len(), 2);
+        assert_eq!(deployments[1].checksum, "def");
+        assert_eq!(deployments[1].timestamp, 100);
+    }
+}
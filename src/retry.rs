@@ -0,0 +1,92 @@
+use std::thread;
+use std::time::Duration;
+
+const INITIAL_DELAY: Duration = Duration::from_millis(10);
+
+/// Runs `action` until it succeeds or `max_attempts` have been made. On
+/// failure, sleeps for a delay that starts at 10ms and doubles after
+/// every attempt, capped at `backoff_ceiling` (left to grow unbounded if
+/// `None`), then retries.
+///
+/// Meant for transient early-boot races (d-bus not ready yet, an ostree
+/// lock held briefly by another process) rather than permanent failures.
+pub fn retry<T, E: std::fmt::Display>(
+    max_attempts: u32,
+    backoff_ceiling: Option<Duration>,
+    mut action: impl FnMut() -> Result<T, E>,
+) -> Result<T, E> {
+    let mut delay = backoff_ceiling.map_or(INITIAL_DELAY, |ceiling| INITIAL_DELAY.min(ceiling));
+    let mut attempt = 1;
+    loop {
+        match action() {
+            Ok(value) => return Ok(value),
+            Err(err) if attempt < max_attempts.max(1) => {
+                log::warn!(
+                    "attempt {attempt}/{max_attempts} failed: {err}, retrying in {delay:?}"
+                );
+                thread::sleep(delay);
+                delay = match backoff_ceiling {
+                    Some(ceiling) => (delay * 2).min(ceiling),
+                    None => delay * 2,
+                };
+                attempt += 1;
+            }
+            Err(err) => return Err(err),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::cell::Cell;
+    use std::time::Instant;
+
+    #[test]
+    fn succeeds_on_first_try() {
+        let calls = Cell::new(0);
+        let result = retry(3, None, || {
+            calls.set(calls.get() + 1);
+            Ok::<_, &str>("ok")
+        });
+        assert_eq!(result, Ok("ok"));
+        assert_eq!(calls.get(), 1);
+    }
+
+    #[test]
+    fn succeeds_after_retrying() {
+        let calls = Cell::new(0);
+        let result = retry(5, Some(Duration::from_millis(1)), || {
+            calls.set(calls.get() + 1);
+            if calls.get() < 3 {
+                Err("not yet")
+            } else {
+                Ok("ok")
+            }
+        });
+        assert_eq!(result, Ok("ok"));
+        assert_eq!(calls.get(), 3);
+    }
+
+    #[test]
+    fn fails_until_max_attempts_then_returns_err() {
+        let calls = Cell::new(0);
+        let result = retry(3, Some(Duration::from_millis(1)), || {
+            calls.set(calls.get() + 1);
+            Err::<(), _>("always fails")
+        });
+        assert_eq!(result, Err("always fails"));
+        assert_eq!(calls.get(), 3);
+    }
+
+    #[test]
+    fn delay_is_capped_by_backoff_ceiling() {
+        // With no ceiling, 10 attempts of a 10ms-doubling delay would take
+        // over 5 seconds; a 1ms ceiling keeps the whole retry well under
+        // a second, proving the delay doesn't grow unbounded.
+        let start = Instant::now();
+        let result = retry(10, Some(Duration::from_millis(1)), || Err::<(), _>("fail"));
+        assert_eq!(result, Err("fail"));
+        assert!(start.elapsed() < Duration::from_secs(1));
+    }
+}
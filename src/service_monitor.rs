@@ -0,0 +1,86 @@
+/// Outcome of [`check`]. Existence/enablement failures (steps 1/2) are
+/// "critical": the unit was removed or disabled out from under greenboot
+/// and needs manual intervention, so step 3 is skipped entirely for that
+/// unit. A failure only at step 3 (the unit exists and is enabled but
+/// isn't actually running) is "recoverable": it defers to the normal
+/// restart/reboot path instead of demanding intervention.
+#[derive(Debug, PartialEq, Eq)]
+pub enum ServiceMonitorResult {
+    Ok,
+    CriticalFailure(Vec<String>),
+    RecoverableFailure(Vec<String>),
+}
+
+/// Checks each of `units` in turn:
+/// 1. does the unit exist
+/// 2. is it enabled (`auto_start`)
+/// 3. is it actually running (`active`)
+pub fn check(units: &[String]) -> ServiceMonitorResult {
+    let mut critical = Vec::new();
+    let mut recoverable = Vec::new();
+
+    for unit in units {
+        match systemctl::exists(unit) {
+            Ok(true) => {}
+            Ok(false) => {
+                log::warn!("service {unit} does not exist");
+                critical.push(unit.clone());
+                continue;
+            }
+            Err(err) => {
+                log::error!("error checking existence of {unit}: {err}");
+                critical.push(unit.clone());
+                continue;
+            }
+        }
+
+        let details = match systemctl::Unit::from_systemctl(unit) {
+            Ok(details) => details,
+            Err(err) => {
+                log::error!("error fetching {unit} details: {err}");
+                critical.push(unit.clone());
+                continue;
+            }
+        };
+
+        if details.auto_start != systemctl::AutoStartStatus::Enabled {
+            log::warn!("service {unit} is not enabled");
+            critical.push(unit.clone());
+            continue;
+        }
+
+        if !is_healthy(details.active) {
+            log::warn!("service {unit} is not healthy (active={})", details.active);
+            recoverable.push(unit.clone());
+        }
+    }
+
+    if !critical.is_empty() {
+        return ServiceMonitorResult::CriticalFailure(critical);
+    }
+    if !recoverable.is_empty() {
+        return ServiceMonitorResult::RecoverableFailure(recoverable);
+    }
+    ServiceMonitorResult::Ok
+}
+
+/// `active` is healthy; anything else (`failed`/`inactive` included) is a
+/// recoverable failure.
+fn is_healthy(active: bool) -> bool {
+    active
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn active_is_healthy() {
+        assert!(is_healthy(true));
+    }
+
+    #[test]
+    fn inactive_is_unhealthy() {
+        assert!(!is_healthy(false));
+    }
+}